@@ -1,9 +1,12 @@
 use std::error::Error;
 use std::io::IsTerminal;
+use std::path::Path;
 
 use clap::Parser;
 use colored::Colorize;
+use ignore::WalkBuilder;
 use regex::Regex;
+use serde_json::json;
 
 #[derive(Parser)]
 #[command(name = "rg")]
@@ -12,12 +15,20 @@ use regex::Regex;
 struct Args {
     /// Pattern to search for
     #[arg(value_name = "PATTERN")]
-    pattern: String,
+    pattern: Option<String>,
 
     /// Files to search
     #[arg(value_name = "FILE")]
     file: Vec<String>,
 
+    /// Pattern to search for (repeatable); with -e/-f every positional argument is a FILE, and a line matches if it matches any given pattern
+    #[arg(short = 'e', long = "regexp", value_name = "PATTERN")]
+    patterns: Vec<String>,
+
+    /// Read patterns, one per line, from PATTERNFILE; every positional argument is then a FILE
+    #[arg(short = 'f', long = "file", value_name = "PATTERNFILE")]
+    pattern_file: Option<String>,
+
     /// Show line numbers with output lines
     #[arg(short = 'n', long = "line-number")]
     show_line_numbers: bool,
@@ -38,13 +49,49 @@ struct Args {
     #[arg(short = 'l', long = "files-with-matches", conflicts_with_all = ["show_line_numbers", "count"])]
     files_with_matches: bool,
 
+    /// Print only the matched portion of each line, one match per output line
+    #[arg(short = 'o', long = "only-matching", conflicts_with_all = ["count", "files_with_matches"])]
+    only_matching: bool,
+
     /// Matches only lines containing the whole pattern, preceded or followed by non-word characters
     #[arg(short = 'w', long = "word-regexp")]
     whole_words: bool,
 
+    /// Recursively search directories, descending into subdirectories
+    #[arg(short = 'r', long = "recursive")]
+    recursive: bool,
+
+    /// Treat PATTERN as a literal string instead of a regular expression
+    #[arg(short = 'F', long = "fixed-strings")]
+    fixed_strings: bool,
+
     #[arg(long = "color", default_value = "auto", value_name = "WHEN")]
     #[arg(value_parser = ["auto",  "always", "never"])]
     color: String,
+
+    /// Print NUM lines of trailing context after each match
+    #[arg(short = 'A', long = "after-context", value_name = "NUM")]
+    after_context: Option<usize>,
+
+    /// Print NUM lines of leading context before each match
+    #[arg(short = 'B', long = "before-context", value_name = "NUM")]
+    before_context: Option<usize>,
+
+    /// Print NUM lines of context before and after each match (overrides -A/-B)
+    #[arg(short = 'C', long = "context", value_name = "NUM")]
+    context: Option<usize>,
+
+    /// Emit machine-readable JSON Lines output instead of human-formatted text
+    #[arg(long = "json", conflicts_with_all = ["count", "files_with_matches"])]
+    json: bool,
+
+    /// Don't respect .gitignore/.ignore files or skip hidden entries while walking directories
+    #[arg(long = "no-ignore")]
+    no_ignore: bool,
+
+    /// When walking directories with -r, only search files of the given type (e.g. rust, py, md); files named directly are always searched
+    #[arg(short = 't', long = "type", value_name = "LANG")]
+    file_type: Option<String>,
 }
 
 fn main() {
@@ -56,30 +103,164 @@ fn main() {
 }
 
 /// Search for a pattern in files
-fn run(args: Args) -> Result<(), Box<dyn Error>> {
+fn run(mut args: Args) -> Result<(), Box<dyn Error>> {
+    if let Some(lang) = &args.file_type {
+        if extension_for_type(lang).is_none() {
+            return Err(format!("unrecognized --type '{lang}'").into());
+        }
+    }
+
+    resolve_positional_args(&mut args);
+
     let regex = get_regex(&args)?;
-    let show_filename = args.file.len() > 1;
+    let show_filename = args.file.len() > 1 || args.recursive;
 
-    for file in &args.file {
-        if let Err(e) = process_file(file, &args, &regex, show_filename) {
-            eprintln!("{}: {}", file, e);
+    let files: Vec<String> = if args.file.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        args.file.clone()
+    };
+
+    for file in &files {
+        let path = Path::new(file);
+        if args.recursive && file != "-" && path.is_dir() {
+            walk_dir(path, &args, &regex, show_filename);
+        } else if let Err(e) = process_file(file, &args, &regex, show_filename) {
+            eprintln!("{}: {}", display_name(file), e);
         }
     }
     Ok(())
 }
 
-/// Create regex for highlighting and whole-word matching if needed
-fn get_regex(args: &Args) -> Result<Regex, Box<dyn Error>> {
-    let pattern = if args.whole_words {
-        format!(r"\b{}\b", regex::escape(&args.pattern))
+/// clap fills the `pattern` positional before `file` regardless of intent. When `-e`/`-f`
+/// supply the pattern(s) instead, whatever bare word clap put in `pattern` is actually the
+/// first FILE, so shift it back onto the front of the file list.
+fn resolve_positional_args(args: &mut Args) {
+    if !args.patterns.is_empty() || args.pattern_file.is_some() {
+        if let Some(leading_file) = args.pattern.take() {
+            args.file.insert(0, leading_file);
+        }
+    }
+}
+
+/// Read the contents of `file`, or standard input when `file` is `-`
+fn read_source(file: &str) -> std::io::Result<String> {
+    if file == "-" {
+        use std::io::Read;
+        let mut contents = String::new();
+        std::io::stdin().lock().read_to_string(&mut contents)?;
+        Ok(contents)
     } else {
-        regex::escape(&args.pattern).to_string()
+        std::fs::read_to_string(file)
+    }
+}
+
+/// The label to show for `file` in output prefixes and error messages
+fn display_name(file: &str) -> &str {
+    if file == "-" { "(standard input)" } else { file }
+}
+
+/// Walk a directory depth-first, running `process_file` on every regular file found
+///
+/// Respects `.gitignore`/`.ignore` and skips hidden entries unless `--no-ignore` is set, and
+/// prunes ignored directories before descending into them so large trees (`target/`, `.git/`)
+/// are skipped entirely rather than merely filtered afterwards.
+fn walk_dir(dir: &Path, args: &Args, regex: &Regex, show_filename: bool) {
+    let walker = WalkBuilder::new(dir)
+        .git_ignore(!args.no_ignore)
+        .ignore(!args.no_ignore)
+        .hidden(!args.no_ignore)
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("{}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        if !matches_file_type(path, args.file_type.as_deref()) {
+            continue;
+        }
+
+        let file = path.to_string_lossy();
+        if let Err(e) = process_file(&file, args, regex, show_filename) {
+            eprintln!("{}: {}", file, e);
+        }
+    }
+}
+
+/// Check whether `path`'s extension matches the built-in extension for `lang` (as given to
+/// `-t`/`--type`). No `--type` at all matches everything; `lang` is assumed to already be
+/// a recognized value, validated up front in `run` via `extension_for_type`.
+fn matches_file_type(path: &Path, lang: Option<&str>) -> bool {
+    let Some(lang) = lang else {
+        return true;
     };
 
+    let extension = extension_for_type(lang).expect("file_type is validated in run before walking");
+    path.extension().and_then(|e| e.to_str()) == Some(extension)
+}
+
+/// The built-in file extension for a `-t`/`--type` value, e.g. `"rust"` -> `"rs"`.
+/// Returns `None` for an unrecognized value.
+fn extension_for_type(lang: &str) -> Option<&'static str> {
+    match lang {
+        "rust" => Some("rs"),
+        "py" | "python" => Some("py"),
+        "md" | "markdown" => Some("md"),
+        _ => None,
+    }
+}
+
+/// Create regex for highlighting and whole-word matching if needed
+///
+/// Collects PATTERN, any `-e`/`--regexp` values, and the lines of a `-f`/`--file` pattern file
+/// into one combined alternation, so a line matches if it matches any of them.
+fn get_regex(args: &Args) -> Result<Regex, Box<dyn Error>> {
+    let mut raw_patterns = Vec::new();
+    raw_patterns.extend(args.pattern.clone());
+    raw_patterns.extend(args.patterns.iter().cloned());
+
+    if let Some(path) = &args.pattern_file {
+        let contents = std::fs::read_to_string(path)?;
+        raw_patterns.extend(contents.lines().map(str::to_string));
+    }
+
+    if raw_patterns.is_empty() {
+        return Err("no pattern given: provide PATTERN, -e/--regexp, or -f/--file".into());
+    }
+
+    let patterns: Vec<String> = raw_patterns
+        .iter()
+        .map(|pattern| {
+            let pattern = if args.fixed_strings {
+                regex::escape(pattern)
+            } else {
+                pattern.clone()
+            };
+
+            if args.whole_words {
+                format!(r"\b{}\b", pattern)
+            } else {
+                pattern
+            }
+        })
+        .collect();
+
+    let combined = patterns.join("|");
+
     let regex = if args.case_insensitive {
-        Regex::new(&format!(r"(?i){}", pattern))?
+        Regex::new(&format!(r"(?i){}", combined))?
     } else {
-        Regex::new(&pattern)?
+        Regex::new(&combined)?
     };
 
     Ok(regex)
@@ -92,34 +273,84 @@ fn process_file(
     regex: &Regex,
     show_filename: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let contents = std::fs::read_to_string(file)?;
+    let contents = read_source(file)?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let matched: Vec<bool> = lines.iter().map(|line| get_matches(args, line, regex)).collect();
+    let name = display_name(file);
     let mut count = 0;
-    let use_color = should_use_color(&args.color);
+    let use_color = !args.json && should_use_color(&args.color);
+
+    let context_requested =
+        args.context.is_some() || args.after_context.is_some() || args.before_context.is_some();
+    let after_context = args.context.or(args.after_context).unwrap_or(0);
+    let before_context = args.context.or(args.before_context).unwrap_or(0);
+    let mut last_printed: Option<usize> = None;
 
-    for (index, line) in contents.lines().enumerate() {
-        if get_matches(args, line, regex) {
-            if args.files_with_matches {
-                println!("{file}");
+    if args.json {
+        println!("{}", json!({"type": "begin", "data": {"path": name}}));
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        if matched[index] {
+            if args.json {
+                let submatches: Vec<_> = regex
+                    .find_iter(line)
+                    .map(|mat| json!({"match": mat.as_str(), "start": mat.start(), "end": mat.end()}))
+                    .collect();
+                println!(
+                    "{}",
+                    json!({
+                        "type": "match",
+                        "data": {
+                            "path": name,
+                            "line_number": index + 1,
+                            "lines": line,
+                            "submatches": submatches,
+                        }
+                    })
+                );
+                count += 1;
+            } else if args.files_with_matches {
+                println!("{name}");
                 return Ok(());
             } else if args.count {
                 count += 1;
             } else {
-                print_match(
-                    file,
-                    index,
-                    line,
-                    regex,
-                    args.show_line_numbers,
-                    show_filename,
-                    use_color,
-                );
+                let start = index.saturating_sub(before_context);
+                let end = (index + after_context).min(lines.len() - 1);
+
+                if should_emit_separator(context_requested, last_printed, start) {
+                    println!("--");
+                }
+
+                let from = match last_printed {
+                    Some(last) if last + 1 > start => last + 1,
+                    _ => start,
+                };
+
+                for (i, line) in lines.iter().enumerate().skip(from).take(end + 1 - from) {
+                    let options = PrintOptions {
+                        show_line_number: args.show_line_numbers,
+                        show_filename,
+                        use_color,
+                        is_match: matched[i],
+                        only_matching: args.only_matching,
+                    };
+                    print_match(name, i, line, regex, &options);
+                }
+                last_printed = Some(end);
             }
         }
     }
 
-    if args.count {
+    if args.json {
+        println!(
+            "{}",
+            json!({"type": "end", "data": {"path": name, "stats": {"matches": count}}})
+        );
+    } else if args.count {
         if show_filename {
-            println!("{file}:{count}");
+            println!("{name}:{count}");
         } else {
             println!("{count}");
         }
@@ -127,37 +358,65 @@ fn process_file(
     Ok(())
 }
 
+/// Whether a `--` group separator should be printed before the next context block.
+///
+/// Only applies when the user actually asked for context via `-A`/`-B`/`-C`; otherwise
+/// non-adjacent matches are printed back-to-back with no separator, matching plain grep.
+fn should_emit_separator(context_requested: bool, last_printed: Option<usize>, start: usize) -> bool {
+    context_requested && last_printed.is_some_and(|last| start > last + 1)
+}
+
 /// Check if a line matches the pattern
 fn get_matches(args: &Args, line: &str, regex: &Regex) -> bool {
     let matches = regex.is_match(line);
     if args.invert_match { !matches } else { matches }
 }
 
-/// Print the matches of pattern in file to the output
-fn print_match(
-    file: &str,
-    index: usize,
-    line: &str,
-    regex: &Regex,
+/// Formatting flags for a single line of `print_match` output
+///
+/// `is_match` distinguishes an actual match line (`:` separator, highlighted) from a
+/// context line pulled in by `-A`/`-B`/`-C` (`-` separator, unhighlighted). `only_matching`
+/// prints just the matched substring(s) of a match line, one per output line, instead of
+/// the whole line.
+struct PrintOptions {
     show_line_number: bool,
     show_filename: bool,
     use_color: bool,
-) {
-    let prefix = if show_filename {
-        if show_line_number {
-            format!("{file}:{}:", index + 1)
+    is_match: bool,
+    only_matching: bool,
+}
+
+/// Print the matches of pattern in file to the output
+fn print_match(file: &str, index: usize, line: &str, regex: &Regex, options: &PrintOptions) {
+    let sep = if options.is_match { ':' } else { '-' };
+
+    let prefix = if options.show_filename {
+        if options.show_line_number {
+            format!("{file}{sep}{}{sep}", index + 1)
         } else {
-            format!("{file}:")
+            format!("{file}{sep}")
         }
     } else {
-        if show_line_number {
-            format!("{}:", index + 1)
+        if options.show_line_number {
+            format!("{}{sep}", index + 1)
         } else {
             String::new()
         }
     };
 
-    let output = if use_color {
+    if options.only_matching && options.is_match {
+        for mat in regex.find_iter(line) {
+            let output = if options.use_color {
+                mat.as_str().red().bold().to_string()
+            } else {
+                mat.as_str().to_string()
+            };
+            println!("{prefix}{output}");
+        }
+        return;
+    }
+
+    let output = if options.use_color && options.is_match {
         highlight_matches(line, regex)
     } else {
         line.to_string()
@@ -170,7 +429,7 @@ fn should_use_color(color_option: &str) -> bool {
     match color_option {
         "always" => true,
         "never" => false,
-        "auto" | _ => std::io::stdout().is_terminal(),
+        _ => std::io::stdout().is_terminal(),
     }
 }
 
@@ -189,3 +448,40 @@ fn highlight_matches(line: &str, regex: &Regex) -> String {
     result.push_str(&line[last_match..]);
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regexp_flag_does_not_swallow_the_file_argument() {
+        let mut args = Args::try_parse_from(["rg", "-e", "foo", "selfref.txt"]).unwrap();
+        resolve_positional_args(&mut args);
+        assert_eq!(args.pattern, None);
+        assert_eq!(args.file, vec!["selfref.txt".to_string()]);
+    }
+
+    #[test]
+    fn pattern_file_flag_does_not_swallow_the_file_argument() {
+        let mut args = Args::try_parse_from(["rg", "-f", "patterns.txt", "src1.txt"]).unwrap();
+        resolve_positional_args(&mut args);
+        assert_eq!(args.pattern, None);
+        assert_eq!(args.file, vec!["src1.txt".to_string()]);
+    }
+
+    #[test]
+    fn plain_pattern_positional_is_unaffected() {
+        let mut args = Args::try_parse_from(["rg", "foo", "file.txt"]).unwrap();
+        resolve_positional_args(&mut args);
+        assert_eq!(args.pattern, Some("foo".to_string()));
+        assert_eq!(args.file, vec!["file.txt".to_string()]);
+    }
+
+    #[test]
+    fn separator_only_emitted_when_context_was_requested() {
+        assert!(!should_emit_separator(false, Some(0), 2));
+        assert!(should_emit_separator(true, Some(0), 2));
+        assert!(!should_emit_separator(true, Some(0), 1));
+        assert!(!should_emit_separator(true, None, 0));
+    }
+}